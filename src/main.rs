@@ -4,49 +4,281 @@
 // std
 use std::collections::HashMap;
 use std::fs::{canonicalize, File};
-use std::io::{prelude::*, BufReader};
+use std::io::{prelude::*, BufReader, SeekFrom};
 use std::path::PathBuf;
+use std::str::FromStr;
 
 // 3rd party
 use clap::Parser;
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
 use tabled::{Style, Table, Tabled};
-
-/// Group identifier, determines the JSON key which is used for grouping the output
-static GROUP_ID: &str = "type";
+use url::Url;
 
 /// CLI tool that analyses a log file.
 ///
-/// The log file must be a text file where each line forms a valid JSON object.
-/// Each JSON object is expected to contain a "type" field of type String.
-/// The tool will provide a statistic which unique "type"s are in the log file as well as the size
-/// of all messages for each type, respectively.
+/// The log file must be a text file where each line forms a valid JSON object; gzip-compressed
+/// files and `http(s)://` URLs are also supported. By default objects are grouped by their
+/// top-level "type" field, but `--group-by` can point at any (optionally nested) key path, or a
+/// comma-separated list of paths for a composite group. For each group the tool reports message
+/// count, total/min/max/mean/stddev size, printed as a table, JSON, CSV, or NDJSON via
+/// `--format`. Large files can be analyzed across multiple threads with `--jobs`. Passing
+/// `--schema` switches to schema-driven validation, reporting whitespace-excluded payload sizes,
+/// per-field null/missing/wrong-type counts, and a dedicated "malformed" group for records that
+/// don't match.
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    /// Path to a log file to be analyzed
-    #[clap(parse(from_os_str))]
-    file: PathBuf,
+    /// Path or URL of a log file to be analyzed. Local paths and remote `http(s)://` URLs are
+    /// both supported; `.gz` inputs are decompressed transparently.
+    file: String,
+
+    /// Number of worker threads to use for analysis.
+    ///
+    /// The file is split into up to this many roughly equal byte ranges (at least 1024 bytes
+    /// each), which are scanned concurrently and merged afterwards. Defaults to the number of logical CPUs. Set to 1 to
+    /// use the single-threaded code path.
+    #[clap(long, default_value_t = default_jobs())]
+    jobs: usize,
+
+    /// Output format for the statistics: table, json, csv, or ndjson
+    #[clap(long, default_value = "table")]
+    format: OutputFormat,
+
+    /// JSON key path(s) to group by, e.g. "type" or "meta.level" for a nested field.
+    ///
+    /// Can be given multiple times (comma-separated) to group by a composite of several keys,
+    /// e.g. `--group-by type,request.method`.
+    #[clap(long, use_value_delimiter = true, default_value = "type")]
+    group_by: Vec<String>,
+
+    /// Path to a JSON schema file describing the expected fields and their types.
+    ///
+    /// When set, records are validated against the schema instead of the default ad-hoc
+    /// parsing: the reported size is the serialized payload size excluding formatting
+    /// whitespace, per-field null/missing counts are tracked, and records that don't match the
+    /// schema are counted under a dedicated "malformed" group instead of being dropped.
+    #[clap(long, parse(from_os_str))]
+    schema: Option<PathBuf>,
 }
 
-/// Table entry structure for printing
-#[derive(Tabled)]
+/// Determine the default number of worker threads, based on the number of logical CPUs.
+fn default_jobs() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+/// Where a log file is read from: a local path, or a remote `http(s)://` URL.
+enum DataSource {
+    Path(PathBuf),
+    Url(Url),
+}
+
+impl DataSource {
+    /// Resolve a raw CLI argument into a `DataSource`. Anything that parses as an `http` or
+    /// `https` URL is treated as remote; everything else is treated as a local path.
+    fn parse(raw: &str) -> DataSource {
+        match Url::parse(raw) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => DataSource::Url(url),
+            _ => DataSource::Path(PathBuf::from(raw)),
+        }
+    }
+
+    /// Whether this source's name ends in `.gz` and should be transparently gunzipped.
+    fn is_gzip(&self) -> bool {
+        match self {
+            DataSource::Path(path) => path.extension().is_some_and(|ext| ext == "gz"),
+            DataSource::Url(url) => url.path().ends_with(".gz"),
+        }
+    }
+}
+
+/// Open a `DataSource` for reading, transparently gunzipping `.gz` sources, and stream a remote
+/// body through the same line-by-line reader used for local files so memory stays bounded.
+fn open_reader(source: &DataSource) -> Box<dyn BufRead> {
+    let raw: Box<dyn Read> = match source {
+        DataSource::Path(path) => Box::new(
+            File::open(path)
+                .unwrap_or_else(|_| panic!("Could not open file: {}", path.display())),
+        ),
+        DataSource::Url(url) => Box::new(
+            ureq::get(url.as_str())
+                .call()
+                .unwrap_or_else(|_| panic!("Could not fetch URL: {}", url))
+                .into_reader(),
+        ),
+    };
+
+    if source.is_gzip() {
+        Box::new(BufReader::new(GzDecoder::new(raw)))
+    } else {
+        Box::new(BufReader::new(raw))
+    }
+}
+
+/// Output format for the statistics printed by `print_statistics`.
+#[derive(Clone, Copy)]
+enum OutputFormat {
+    /// Human-readable table (the default)
+    Table,
+    /// A single JSON array of per-group objects with `type`, `count`, `size`, `min`, `max`,
+    /// `mean`, and `stddev` fields
+    Json,
+    /// A `type,count,size,min,max,mean,stddev` header followed by one row per group
+    Csv,
+    /// One JSON object per line, suitable for piping into other line-oriented tools
+    Ndjson,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            _ => Err(format!(
+                "Unknown output format: \"{}\". Expected one of: table, json, csv, ndjson",
+                s
+            )),
+        }
+    }
+}
+
+/// A single parsed log entry, prior to being folded into a `GroupStats` accumulator.
 struct LogEntryStatistic {
+    t: String,
+    size: usize,
+}
+
+/// Running aggregate statistics for one group: message count, total/min/max size, and mean/stddev
+/// of the size, computed online via Welford's algorithm so it never needs to hold every size in
+/// memory and never overflows on a sum-of-squares.
+#[derive(Clone, Copy, Serialize)]
+struct GroupStats {
+    count: usize,
+    sum: usize,
+    min: usize,
+    max: usize,
+    mean: f64,
+    /// Sum of squares of differences from the running mean, per Welford's algorithm
+    m2: f64,
+}
+
+impl GroupStats {
+    /// Create a new accumulator seeded with a single observation.
+    fn new(size: usize) -> Self {
+        GroupStats {
+            count: 1,
+            sum: size,
+            min: size,
+            max: size,
+            mean: size as f64,
+            m2: 0.0,
+        }
+    }
+
+    /// Fold one more observation into this accumulator (Welford's online algorithm).
+    fn add(&mut self, size: usize) {
+        self.count += 1;
+        self.sum += size;
+        self.min = self.min.min(size);
+        self.max = self.max.max(size);
+
+        let delta = size as f64 - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (size as f64 - self.mean);
+    }
+
+    /// Combine two independently accumulated `GroupStats` (Chan et al.'s parallel variant of
+    /// Welford's algorithm), as used to merge the per-worker partials in the parallel code path.
+    fn merge(&self, other: &GroupStats) -> GroupStats {
+        let total = self.count + other.count;
+        let delta = other.mean - self.mean;
+        let mean =
+            (self.count as f64 * self.mean + other.count as f64 * other.mean) / total as f64;
+        let m2 = self.m2
+            + other.m2
+            + delta * delta * (self.count as f64 * other.count as f64) / total as f64;
+
+        GroupStats {
+            count: total,
+            sum: self.sum + other.sum,
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+            mean,
+            m2,
+        }
+    }
+
+    /// Population variance of the accumulated sizes.
+    fn variance(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.m2 / self.count as f64
+        }
+    }
+
+    /// Population standard deviation of the accumulated sizes.
+    fn stddev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+}
+
+/// Table/serialization row combining a group's key with its `GroupStats`.
+#[derive(Tabled, Serialize)]
+struct GroupStatisticRow {
     #[tabled(rename = "Type")]
+    #[serde(rename = "type")]
     t: String,
-    #[tabled(rename = "Size [byte]")]
+    #[tabled(rename = "Count")]
+    count: usize,
+    #[tabled(rename = "Total Size [byte]")]
     size: usize,
+    #[tabled(rename = "Min [byte]")]
+    min: usize,
+    #[tabled(rename = "Max [byte]")]
+    max: usize,
+    #[tabled(rename = "Mean [byte]", display_with = "format_two_decimals")]
+    mean: f64,
+    #[tabled(rename = "Stddev [byte]", display_with = "format_two_decimals")]
+    stddev: f64,
+}
+
+impl GroupStatisticRow {
+    fn new(t: String, stats: &GroupStats) -> Self {
+        GroupStatisticRow {
+            t,
+            count: stats.count,
+            size: stats.sum,
+            min: stats.min,
+            max: stats.max,
+            mean: stats.mean,
+            stddev: stats.stddev(),
+        }
+    }
+}
+
+/// Format a float with two decimal places, used for the table's Mean/Stddev columns.
+fn format_two_decimals(value: &f64) -> String {
+    format!("{:.2}", value)
 }
 
 // Implement FromIterator trait to simply collect() all LogEntryStatistics later into a HashMap
-impl FromIterator<LogEntryStatistic> for HashMap<String, usize> {
-    /// Creates a HashMap<String, usize> from an iterator of item type LogEntryStatistic
+impl FromIterator<LogEntryStatistic> for HashMap<String, GroupStats> {
+    /// Creates a HashMap<String, GroupStats> from an iterator of item type LogEntryStatistic
     fn from_iter<I: IntoIterator<Item = LogEntryStatistic>>(iter: I) -> Self {
         let mut hash_map = HashMap::new();
 
         iter.into_iter().for_each(|x| {
-            // Accumulate sizes in HashMap
-            // https://stackoverflow.com/a/30414450/6658448
-            *hash_map.entry(x.t).or_insert(0) += x.size;
+            hash_map
+                .entry(x.t)
+                .and_modify(|stats: &mut GroupStats| stats.add(x.size))
+                .or_insert_with(|| GroupStats::new(x.size));
         });
 
         hash_map
@@ -64,59 +296,87 @@ struct ParseError {
 /// # Arguments
 ///
 /// - `raw` - Raw `String` that should be parsed
+/// - `group_by` - Key path(s) to group by, as given via `--group-by`. Each path is a
+///   dot-separated walk into the JSON object; multiple paths are concatenated into one
+///   composite group key.
 ///
 /// # Returns
 ///
 /// A Result that is a LogEntryStatistic in a successful case, or a ParseError in case the
-/// JSON object could not be parsed.
-fn parse_json(raw: String) -> Result<LogEntryStatistic, ParseError> {
+/// JSON object could not be parsed, or one of the `group_by` paths could not be resolved.
+fn parse_json(raw: String, group_by: &[String]) -> Result<LogEntryStatistic, ParseError> {
     // Error handling for JSON parse errors
     let json: serde_json::Value = if let Ok(x) = serde_json::from_str(&raw) {
         x
     } else {
         return Err(ParseError { context: raw });
     };
-    // Error handling for missing GROUP_ID field
-    let t: String = if let Some(x) = json.get(GROUP_ID) {
-        // Error handling for GROUP_ID value is not a String
-        if let Some(s) = x.as_str() {
-            s.to_string()
-        } else {
-            return Err(ParseError { context: raw });
-        }
-    } else {
-        return Err(ParseError { context: raw });
+
+    let t = match resolve_group_key(&json, group_by) {
+        Some(key) => key,
+        None => return Err(ParseError { context: raw }),
     };
+
     Ok(LogEntryStatistic { t, size: raw.len() })
 }
 
-/// Receive a valid file and read it.
+/// Walk each of the `group_by` key paths into `json` and concatenate the resulting values into
+/// one composite group key, separated by `/`.
 ///
-/// Each line of the file should contain a valid JSON object. Each JSON object consists of a "type"
-/// field, containing a String, and any number of arbitrary additional fields.
+/// Each path is split on `.` and resolved segment-by-segment. String values are used as-is,
+/// numbers and booleans are coerced to their display string. Returns `None` if any segment is
+/// missing or the terminal value isn't a scalar.
+fn resolve_group_key(json: &serde_json::Value, group_by: &[String]) -> Option<String> {
+    let mut parts = Vec::with_capacity(group_by.len());
+
+    for path in group_by {
+        let mut current = json;
+        for segment in path.split('.') {
+            current = current.get(segment)?;
+        }
+
+        let part = match current {
+            serde_json::Value::String(s) => s.clone(),
+            serde_json::Value::Number(n) => n.to_string(),
+            serde_json::Value::Bool(b) => b.to_string(),
+            _ => return None,
+        };
+        parts.push(part);
+    }
+
+    Some(parts.join("/"))
+}
+
+/// Receive a valid reader and read it.
+///
+/// Each line read should contain a valid JSON object in which every `group_by` key path resolves
+/// to a string, number, or boolean; any number of arbitrary additional fields may be present.
 ///
 /// # Arguments
 ///
-/// `file` - A `File` handle used for reading
+/// - `reader` - Any `BufRead` source of NDJSON lines, e.g. a local file or a decompressed
+///   remote body
+/// - `group_by` - Key path(s) to group by, see `parse_json`
 ///
 /// # Returns
 ///
-/// A HashMap with all unique "type"s as keys and the accumulated byte size of the JSON objects for
-/// each type as value.
-fn read_json_objects(file: &File) -> HashMap<String, usize> {
-    //let mut log_statistics: HashMap<String, usize> = HashMap::new();
-    let reader = BufReader::new(file);
-    let log_statistics: HashMap<String, usize> = reader
+/// A HashMap with all unique group keys as keys and the accumulated `GroupStats` of the JSON
+/// objects for each group as value.
+fn read_json_objects(
+    reader: impl BufRead,
+    group_by: &[String],
+) -> HashMap<String, GroupStats> {
+    let log_statistics: HashMap<String, GroupStats> = reader
         .lines()
         // handle line read errors
         .filter_map(|x| {
             x.map_err(|e| println!("Warning: Could not read line from file: \"{}\". Statistics might be unreliable.", e))
                 .ok()
         })
-        .map(parse_json)
+        .map(|raw| parse_json(raw, group_by))
         // handle parse errors
         .filter_map(|x| {
-            x.map_err(|e| println!("Warning: Wrongly formatted object: \"{}\". Object needs to be valid JSON containing a \"{}\" field of type String. Statistics might be unreliable.", e.context, GROUP_ID))
+            x.map_err(|e| println!("Warning: Wrongly formatted object: \"{}\". Object needs to contain valid JSON resolving all of {:?}. Statistics might be unreliable.", e.context, group_by))
                 .ok()
         })
         // Use the `FromIterator` trait here to simply collect() all LogEntryStatistics
@@ -125,22 +385,420 @@ fn read_json_objects(file: &File) -> HashMap<String, usize> {
     log_statistics
 }
 
-/// Print out the log statistics provided as HashMap into a table to stdout.
+/// Receive a valid file and read it using multiple worker threads.
+///
+/// The file is split into up to `jobs` roughly equal byte ranges, capped so that each range
+/// covers at least `MIN_CHUNK_BYTES` bytes. Each range is extended to the next
+/// newline so that no line is split across two workers, then scanned, parsed and accumulated
+/// independently before the partial results are merged into one final map.
+///
+/// # Arguments
+///
+/// - `file_path` - Path of the file to read
+/// - `jobs` - Number of worker threads to split the file across, must be greater than zero
+///
+/// # Returns
+///
+/// A HashMap with all unique group keys as keys and the accumulated `GroupStats` of the JSON
+/// objects for each group as value. Count, total, min and max are identical to what
+/// `read_json_objects` would produce for the same file; mean and stddev agree up to
+/// floating-point rounding.
+fn read_json_objects_parallel(
+    file_path: &PathBuf,
+    jobs: usize,
+    group_by: &[String],
+) -> HashMap<String, GroupStats> {
+    let file_len = File::open(file_path)
+        .and_then(|f| f.metadata())
+        .unwrap_or_else(|_| panic!("Could not read metadata for file: {}", file_path.display()))
+        .len();
+
+    // Cap the number of workers so a huge `--jobs` value against a small file doesn't spawn far
+    // more threads than there is data to keep them busy.
+    let jobs = jobs.max(1).min((file_len / MIN_CHUNK_BYTES).max(1) as usize);
+
+    let boundaries = compute_chunk_boundaries(file_path, file_len, jobs);
+
+    let partials: Vec<HashMap<String, GroupStats>> = std::thread::scope(|scope| {
+        boundaries
+            .windows(2)
+            .map(|range| {
+                let (start, end) = (range[0], range[1]);
+                scope.spawn(move || read_json_objects_range(file_path, start, end, group_by))
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().expect("Worker thread panicked"))
+            .collect()
+    });
+
+    merge_statistics(partials)
+}
+
+/// Minimum number of bytes a single worker is given responsibility for; used to cap `jobs` so we
+/// don't spawn more threads than the file could usefully keep busy.
+const MIN_CHUNK_BYTES: u64 = 1024;
+
+/// Compute `jobs + 1` byte offsets that split a file of length `file_len` into `jobs` ranges.
+///
+/// The first and last offset are always `0` and `file_len`. Every offset in between is nudged
+/// forward to the next newline via `align_to_next_newline`, so each resulting range contains only
+/// whole lines.
+fn compute_chunk_boundaries(file_path: &PathBuf, file_len: u64, jobs: usize) -> Vec<u64> {
+    let chunk_size = file_len / jobs as u64;
+    let mut boundaries = Vec::with_capacity(jobs + 1);
+    boundaries.push(0);
+    for i in 1..jobs {
+        boundaries.push(align_to_next_newline(file_path, chunk_size * i as u64, file_len));
+    }
+    boundaries.push(file_len);
+    boundaries
+}
+
+/// Nudge `pos` forward to the offset right after the next newline, so it never lands in the
+/// middle of a line. Returns `file_len` if no further newline is found.
+fn align_to_next_newline(file_path: &PathBuf, pos: u64, file_len: u64) -> u64 {
+    if pos >= file_len {
+        return file_len;
+    }
+
+    let mut file = File::open(file_path).expect("Could not reopen file for chunk alignment");
+    file.seek(SeekFrom::Start(pos))
+        .expect("Could not seek in file");
+
+    let mut buf = Vec::new();
+    match BufReader::new(file).read_until(b'\n', &mut buf) {
+        Ok(0) | Err(_) => file_len,
+        Ok(n) => pos + n as u64,
+    }
+}
+
+/// Read and accumulate statistics for a single `[start, end)` byte range of `file_path`.
+///
+/// Used as the per-worker unit of work in `read_json_objects_parallel`.
+fn read_json_objects_range(
+    file_path: &PathBuf,
+    start: u64,
+    end: u64,
+    group_by: &[String],
+) -> HashMap<String, GroupStats> {
+    let mut file = File::open(file_path).expect("Could not reopen file for chunked read");
+    file.seek(SeekFrom::Start(start))
+        .expect("Could not seek in file");
+    let reader = BufReader::new(file.take(end - start));
+
+    reader
+        .lines()
+        // handle line read errors
+        .filter_map(|x| {
+            x.map_err(|e| println!("Warning: Could not read line from file: \"{}\". Statistics might be unreliable.", e))
+                .ok()
+        })
+        .map(|raw| parse_json(raw, group_by))
+        // handle parse errors
+        .filter_map(|x| {
+            x.map_err(|e| println!("Warning: Wrongly formatted object: \"{}\". Object needs to contain valid JSON resolving all of {:?}. Statistics might be unreliable.", e.context, group_by))
+                .ok()
+        })
+        // Use the `FromIterator` trait here to simply collect() all LogEntryStatistics
+        .collect()
+}
+
+/// Merge the partial per-worker maps produced by `read_json_objects_parallel` into one final map.
+///
+/// Merging uses the parallel Welford combine in `GroupStats::merge`, which is associative, so the
+/// result is the same regardless of how many partial maps are combined or in which order: count,
+/// total, min and max exactly, mean and stddev up to floating-point rounding.
+fn merge_statistics(partials: Vec<HashMap<String, GroupStats>>) -> HashMap<String, GroupStats> {
+    let mut merged: HashMap<String, GroupStats> = HashMap::new();
+    for partial in partials {
+        for (key, stats) in partial {
+            merged
+                .entry(key)
+                .and_modify(|existing: &mut GroupStats| *existing = existing.merge(&stats))
+                .or_insert(stats);
+        }
+    }
+    merged
+}
+
+/// Print out the log statistics provided as HashMap to stdout, in the given `OutputFormat`.
 ///
-/// The HashMap is expected to hold the "type" as key and the size in bytes as value.
+/// The HashMap is expected to hold the group key as key and its accumulated `GroupStats` as value.
+/// All formats are derived from the same HashMap, so they are guaranteed to agree on content.
 ///
 /// # Arguments
 ///
-/// - `statistics` - A HashMap containing a String key and an usize value
-fn print_statistics(statistics: &HashMap<String, usize>) {
+/// - `statistics` - A HashMap containing a String key and a GroupStats value
+/// - `format` - The `OutputFormat` to print the statistics in
+fn print_statistics(statistics: &HashMap<String, GroupStats>, format: OutputFormat) {
+    match format {
+        OutputFormat::Table => print_table(statistics),
+        OutputFormat::Json => print_json(statistics),
+        OutputFormat::Csv => print_csv(statistics),
+        OutputFormat::Ndjson => print_ndjson(statistics),
+    }
+}
+
+/// Print the statistics as a human-readable, rounded-style table.
+fn print_table(statistics: &HashMap<String, GroupStats>) {
     println!(
         "{}",
         // Map HashMap contents into table structure
-        Table::new(statistics.iter().map(|(key, value)| {
-            LogEntryStatistic {
-                t: key.to_string(),
-                size: *value,
+        Table::new(
+            statistics
+                .iter()
+                .map(|(key, stats)| GroupStatisticRow::new(key.to_string(), stats))
+        )
+        .with(Style::rounded())
+    );
+}
+
+/// Print the statistics as a single JSON array of per-group statistic objects.
+fn print_json(statistics: &HashMap<String, GroupStats>) {
+    let entries: Vec<GroupStatisticRow> = statistics
+        .iter()
+        .map(|(key, stats)| GroupStatisticRow::new(key.to_string(), stats))
+        .collect();
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&entries).expect("Could not serialize statistics to JSON")
+    );
+}
+
+/// Quote and escape a single CSV field per RFC 4180, if it contains a comma, quote, or newline.
+fn escape_csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Print the statistics as a CSV header followed by one row per group.
+fn print_csv(statistics: &HashMap<String, GroupStats>) {
+    println!("type,count,size,min,max,mean,stddev");
+    for (key, stats) in statistics {
+        println!(
+            "{},{},{},{},{},{:.2},{:.2}",
+            escape_csv_field(key),
+            stats.count,
+            stats.sum,
+            stats.min,
+            stats.max,
+            stats.mean,
+            stats.stddev()
+        );
+    }
+}
+
+/// Print the statistics as one JSON object per line (newline-delimited JSON).
+fn print_ndjson(statistics: &HashMap<String, GroupStats>) {
+    for (key, stats) in statistics {
+        let entry = GroupStatisticRow::new(key.to_string(), stats);
+        println!(
+            "{}",
+            serde_json::to_string(&entry).expect("Could not serialize entry to JSON")
+        );
+    }
+}
+
+/// The JSON type a schema field is expected to hold.
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum FieldType {
+    String,
+    Number,
+    Bool,
+    Object,
+    Array,
+    Null,
+}
+
+/// One field entry of a `--schema` file.
+#[derive(Deserialize)]
+struct FieldSchema {
+    name: String,
+    #[serde(rename = "type")]
+    field_type: FieldType,
+}
+
+/// A `--schema` file: the set of fields a record is expected to contain, and their types.
+#[derive(Deserialize)]
+struct Schema {
+    fields: Vec<FieldSchema>,
+}
+
+impl Schema {
+    /// Load and parse a `Schema` from the given JSON file.
+    fn load(path: &PathBuf) -> Schema {
+        let raw = std::fs::read_to_string(path)
+            .unwrap_or_else(|_| panic!("Could not read schema file: {}", path.display()));
+        serde_json::from_str(&raw)
+            .unwrap_or_else(|_| panic!("Schema file is not valid JSON: {}", path.display()))
+    }
+}
+
+/// Whether `value`'s JSON type matches `expected`.
+fn type_matches(value: &serde_json::Value, expected: FieldType) -> bool {
+    matches!(
+        (value, expected),
+        (serde_json::Value::String(_), FieldType::String)
+            | (serde_json::Value::Number(_), FieldType::Number)
+            | (serde_json::Value::Bool(_), FieldType::Bool)
+            | (serde_json::Value::Object(_), FieldType::Object)
+            | (serde_json::Value::Array(_), FieldType::Array)
+            | (serde_json::Value::Null, FieldType::Null)
+    )
+}
+
+/// Per-field tally of how often a schema field was present with a matching type, present but
+/// `null`, present with the wrong type, or missing entirely. These four buckets are exhaustive:
+/// they sum to the number of records that reached validation for this field.
+#[derive(Default, Clone, Copy)]
+struct FieldPresence {
+    present: usize,
+    null: usize,
+    wrong_type: usize,
+    missing: usize,
+}
+
+/// Group key under which records that fail schema validation are accumulated.
+static MALFORMED_GROUP: &str = "malformed";
+
+/// Validate `json` against `schema`, updating the per-field `tally` along the way.
+///
+/// Returns `true` if every schema field is present with a matching type (a `null` value counts
+/// as present, but only matches a field declared as `FieldType::Null`).
+fn validate_record(
+    json: &serde_json::Value,
+    schema: &Schema,
+    tally: &mut HashMap<String, FieldPresence>,
+) -> bool {
+    let mut matches_schema = true;
+
+    for field in &schema.fields {
+        let entry = tally.entry(field.name.clone()).or_default();
+        match json.get(&field.name) {
+            None => {
+                entry.missing += 1;
+                matches_schema = false;
+            }
+            Some(serde_json::Value::Null) => {
+                entry.null += 1;
+                matches_schema &= field.field_type == FieldType::Null;
             }
+            Some(value) => {
+                if type_matches(value, field.field_type) {
+                    entry.present += 1;
+                } else {
+                    entry.wrong_type += 1;
+                    matches_schema = false;
+                }
+            }
+        }
+    }
+
+    matches_schema
+}
+
+/// Serialized payload size of `json`, excluding any formatting whitespace of the original line.
+fn compact_size(json: &serde_json::Value) -> usize {
+    serde_json::to_string(json)
+        .map(|s| s.len())
+        .unwrap_or(0)
+}
+
+/// Fold one more observation of `size` into `statistics` under `key`.
+fn add_group_observation(statistics: &mut HashMap<String, GroupStats>, key: String, size: usize) {
+    statistics
+        .entry(key)
+        .and_modify(|stats| stats.add(size))
+        .or_insert_with(|| GroupStats::new(size));
+}
+
+/// Receive a valid reader and read it in schema-driven mode.
+///
+/// Each line is parsed as JSON and validated against `schema`. Records that match are grouped by
+/// `group_by` as usual, with their size taken as the compact serialized payload size rather than
+/// `raw.len()`. Records that fail validation (or that can't be parsed as JSON, or whose
+/// `group_by` path can't be resolved) are accumulated under the `MALFORMED_GROUP` bucket instead
+/// of being dropped.
+///
+/// # Returns
+///
+/// A tuple of the per-group `GroupStats` (including the "malformed" bucket) and the per-field
+/// presence tally collected while validating against the schema.
+fn read_json_objects_with_schema(
+    reader: impl BufRead,
+    schema: &Schema,
+    group_by: &[String],
+) -> (HashMap<String, GroupStats>, HashMap<String, FieldPresence>) {
+    let mut statistics: HashMap<String, GroupStats> = HashMap::new();
+    let mut field_presence: HashMap<String, FieldPresence> = HashMap::new();
+
+    for line in reader.lines() {
+        let raw = match line {
+            Ok(raw) => raw,
+            Err(e) => {
+                println!("Warning: Could not read line from file: \"{}\". Statistics might be unreliable.", e);
+                continue;
+            }
+        };
+
+        let json: serde_json::Value = match serde_json::from_str(&raw) {
+            Ok(json) => json,
+            Err(e) => {
+                println!("Warning: Wrongly formatted object: \"{}\". Object needs to be valid JSON. Counted under the \"{}\" group. ({})", raw, MALFORMED_GROUP, e);
+                add_group_observation(&mut statistics, MALFORMED_GROUP.to_string(), raw.len());
+                continue;
+            }
+        };
+
+        if !validate_record(&json, schema, &mut field_presence) {
+            println!("Warning: Record does not match schema: \"{}\". Counted under the \"{}\" group.", raw, MALFORMED_GROUP);
+            add_group_observation(&mut statistics, MALFORMED_GROUP.to_string(), compact_size(&json));
+            continue;
+        }
+
+        match resolve_group_key(&json, group_by) {
+            Some(key) => add_group_observation(&mut statistics, key, compact_size(&json)),
+            None => {
+                println!("Warning: Wrongly formatted object: \"{}\". Object needs to contain valid JSON resolving all of {:?}. Counted under the \"{}\" group.", raw, group_by, MALFORMED_GROUP);
+                add_group_observation(&mut statistics, MALFORMED_GROUP.to_string(), compact_size(&json));
+            }
+        }
+    }
+
+    (statistics, field_presence)
+}
+
+/// Table row for one field's presence tally.
+#[derive(Tabled)]
+struct FieldPresenceRow {
+    #[tabled(rename = "Field")]
+    field: String,
+    #[tabled(rename = "Present")]
+    present: usize,
+    #[tabled(rename = "Null")]
+    null: usize,
+    #[tabled(rename = "Wrong Type")]
+    wrong_type: usize,
+    #[tabled(rename = "Missing")]
+    missing: usize,
+}
+
+/// Print the per-field presence tally collected by `read_json_objects_with_schema`.
+fn print_field_presence(tally: &HashMap<String, FieldPresence>) {
+    println!("\nField presence:");
+    println!(
+        "{}",
+        Table::new(tally.iter().map(|(field, presence)| FieldPresenceRow {
+            field: field.to_string(),
+            present: presence.present,
+            null: presence.null,
+            wrong_type: presence.wrong_type,
+            missing: presence.missing,
         }))
         .with(Style::rounded())
     );
@@ -148,20 +806,247 @@ fn print_statistics(statistics: &HashMap<String, usize>) {
 
 fn main() {
     let args = Args::parse();
+    let source = DataSource::parse(&args.file);
+
+    let log_statistics = match source {
+        DataSource::Path(path) => {
+            let absolute_path = canonicalize(&path).expect(&format!(
+                "FILE argument was not understood: {}. Does the file exist?",
+                path.display()
+            ));
+            println!("Using logfile {}\n", absolute_path.display());
+
+            let source = DataSource::Path(absolute_path.clone());
+            if let Some(schema_path) = &args.schema {
+                if args.jobs > 1 {
+                    println!("Warning: parallel analysis (--jobs > 1) is not supported when validating against a schema; falling back to a single worker.");
+                }
+                let schema = Schema::load(schema_path);
+                let (statistics, field_presence) =
+                    read_json_objects_with_schema(open_reader(&source), &schema, &args.group_by);
+                print_statistics(&statistics, args.format);
+                print_field_presence(&field_presence);
+                return;
+            }
+
+            if args.jobs > 1 && source.is_gzip() {
+                println!("Warning: parallel analysis (--jobs > 1) is not supported for gzip-compressed sources; falling back to a single worker.");
+            }
+
+            if args.jobs > 1 && !source.is_gzip() {
+                read_json_objects_parallel(&absolute_path, args.jobs, &args.group_by)
+            } else {
+                read_json_objects(open_reader(&source), &args.group_by)
+            }
+        }
+        DataSource::Url(url) => {
+            println!("Using remote logfile {}\n", url);
+
+            if let Some(schema_path) = &args.schema {
+                if args.jobs > 1 {
+                    println!("Warning: parallel analysis (--jobs > 1) is not supported when validating against a schema; falling back to a single worker.");
+                }
+                let schema = Schema::load(schema_path);
+                let source = DataSource::Url(url);
+                let (statistics, field_presence) =
+                    read_json_objects_with_schema(open_reader(&source), &schema, &args.group_by);
+                print_statistics(&statistics, args.format);
+                print_field_presence(&field_presence);
+                return;
+            }
+
+            if args.jobs > 1 {
+                println!("Warning: parallel analysis (--jobs > 1) is not supported for remote sources; falling back to a single worker.");
+            }
+            read_json_objects(open_reader(&DataSource::Url(url)), &args.group_by)
+        }
+    };
+
+    print_statistics(&log_statistics, args.format);
+}
 
-    // Determine absolute path
-    let absolute_path = canonicalize(&args.file).expect(&format!(
-        "FILE argument was not understood: {}. Does the file exist?",
-        &args.file.display()
-    ));
-    println!("Using logfile {}\n", &absolute_path.display());
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let file = File::open(&absolute_path).expect(&format!(
-        "Could not open file: {}",
-        &absolute_path.display()
-    ));
+    #[test]
+    fn group_stats_merge_matches_sequential_add() {
+        let sizes = [10usize, 20, 5, 40, 15, 7, 33, 12];
 
-    let log_statistics = read_json_objects(&file);
+        let mut sequential = GroupStats::new(sizes[0]);
+        for &size in &sizes[1..] {
+            sequential.add(size);
+        }
+
+        // Split the same observations across two accumulators and merge them, mirroring how
+        // `merge_statistics` combines per-worker partials in the parallel code path.
+        let mut a = GroupStats::new(sizes[0]);
+        for &size in &sizes[1..4] {
+            a.add(size);
+        }
+        let mut b = GroupStats::new(sizes[4]);
+        for &size in &sizes[5..] {
+            b.add(size);
+        }
+        let merged = a.merge(&b);
+
+        assert_eq!(merged.count, sequential.count);
+        assert_eq!(merged.sum, sequential.sum);
+        assert_eq!(merged.min, sequential.min);
+        assert_eq!(merged.max, sequential.max);
+        assert!((merged.mean - sequential.mean).abs() < 1e-9);
+        assert!((merged.variance() - sequential.variance()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn parallel_analysis_matches_single_threaded_for_multiple_jobs() {
+        let path = std::env::temp_dir().join(format!(
+            "log_analyzer_test_{}_{}.ndjson",
+            std::process::id(),
+            "parallel_matches_sequential"
+        ));
+        let group_by = vec!["type".to_string()];
+
+        let lines: Vec<String> = (0..500)
+            .map(|i| {
+                let t = ["a", "b", "c"][i % 3];
+                format!("{{\"type\":\"{}\",\"n\":{}}}", t, i)
+            })
+            .collect();
+        std::fs::write(&path, lines.join("\n") + "\n").expect("Could not write test fixture");
+
+        let file = File::open(&path).expect("Could not open test fixture");
+        let sequential = read_json_objects(BufReader::new(file), &group_by);
+        let parallel = read_json_objects_parallel(&path, 4, &group_by);
+
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(sequential.len(), parallel.len());
+        for (key, seq_stats) in &sequential {
+            let par_stats = parallel
+                .get(key)
+                .expect("group present in sequential result missing from parallel result");
+            assert_eq!(seq_stats.count, par_stats.count);
+            assert_eq!(seq_stats.sum, par_stats.sum);
+            assert_eq!(seq_stats.min, par_stats.min);
+            assert_eq!(seq_stats.max, par_stats.max);
+            assert!((seq_stats.mean - par_stats.mean).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn escape_csv_field_quotes_only_when_needed() {
+        assert_eq!(escape_csv_field("plain"), "plain");
+        assert_eq!(escape_csv_field("with,comma"), "\"with,comma\"");
+        assert_eq!(escape_csv_field("with\nnewline"), "\"with\nnewline\"");
+        assert_eq!(
+            escape_csv_field("with \"quotes\""),
+            "\"with \"\"quotes\"\"\""
+        );
+    }
 
-    print_statistics(&log_statistics);
+    #[test]
+    fn resolve_group_key_walks_nested_paths_and_joins_composites() {
+        let json: serde_json::Value = serde_json::from_str(
+            r#"{"type":"error","user":{"id":42},"nested":{"deep":{"flag":true}}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            resolve_group_key(&json, &["type".to_string()]),
+            Some("error".to_string())
+        );
+        assert_eq!(
+            resolve_group_key(&json, &["user.id".to_string()]),
+            Some("42".to_string())
+        );
+        assert_eq!(
+            resolve_group_key(
+                &json,
+                &["type".to_string(), "nested.deep.flag".to_string()]
+            ),
+            Some("error/true".to_string())
+        );
+        assert_eq!(resolve_group_key(&json, &["missing.path".to_string()]), None);
+        assert_eq!(resolve_group_key(&json, &["user".to_string()]), None);
+    }
+
+    #[test]
+    fn data_source_detects_remote_urls_and_gzip_extensions() {
+        assert!(matches!(
+            DataSource::parse("/var/log/app.log"),
+            DataSource::Path(_)
+        ));
+        assert!(matches!(
+            DataSource::parse("https://example.com/app.log"),
+            DataSource::Url(_)
+        ));
+        assert!(matches!(
+            DataSource::parse("http://example.com/app.log"),
+            DataSource::Url(_)
+        ));
+
+        assert!(!DataSource::parse("/var/log/app.log").is_gzip());
+        assert!(DataSource::parse("/var/log/app.log.gz").is_gzip());
+        assert!(!DataSource::parse("https://example.com/app.log").is_gzip());
+        assert!(DataSource::parse("https://example.com/app.log.gz").is_gzip());
+    }
+
+    #[test]
+    fn validate_record_tallies_present_null_wrong_type_and_missing_exhaustively() {
+        let schema = Schema {
+            fields: vec![
+                FieldSchema {
+                    name: "name".to_string(),
+                    field_type: FieldType::String,
+                },
+                FieldSchema {
+                    name: "age".to_string(),
+                    field_type: FieldType::Number,
+                },
+                FieldSchema {
+                    name: "nickname".to_string(),
+                    field_type: FieldType::String,
+                },
+                FieldSchema {
+                    name: "address".to_string(),
+                    field_type: FieldType::Object,
+                },
+            ],
+        };
+
+        let mut tally: HashMap<String, FieldPresence> = HashMap::new();
+
+        // "name" present with matching type, "age" present with the wrong type, "nickname"
+        // present but null, "address" missing entirely.
+        let record: serde_json::Value =
+            serde_json::from_str(r#"{"name":"Ada","age":"not a number","nickname":null}"#)
+                .unwrap();
+        assert!(!validate_record(&record, &schema, &mut tally));
+
+        assert_eq!(tally["name"].present, 1);
+        assert_eq!(tally["name"].wrong_type, 0);
+        assert_eq!(tally["name"].null, 0);
+        assert_eq!(tally["name"].missing, 0);
+
+        assert_eq!(tally["age"].wrong_type, 1);
+        assert_eq!(tally["age"].present, 0);
+
+        assert_eq!(tally["nickname"].null, 1);
+        assert_eq!(tally["nickname"].present, 0);
+
+        assert_eq!(tally["address"].missing, 1);
+        assert_eq!(tally["address"].present, 0);
+
+        // A fully matching record should validate and only add to the "present" bucket.
+        let matching: serde_json::Value = serde_json::from_str(
+            r#"{"name":"Grace","age":40,"nickname":"G","address":{"city":"NYC"}}"#,
+        )
+        .unwrap();
+        assert!(validate_record(&matching, &schema, &mut tally));
+        assert_eq!(tally["name"].present, 2);
+        assert_eq!(tally["age"].present, 1);
+        assert_eq!(tally["nickname"].present, 1);
+        assert_eq!(tally["address"].present, 1);
+    }
 }